@@ -0,0 +1,82 @@
+//! Deterministic, seeded counterpart to `fuzz/fuzz_targets/differential_memory.rs`.
+//!
+//! Unlike the libfuzzer target, this binary takes an explicit `--seed` (default 0) and a
+//! `--iterations` count, generates the same shape of code/memory image via a seeded `StdRng`,
+//! and runs the identical invariant checks from `omo::testing`. On panic it reports the seed
+//! of the failing iteration (`--seed` plus the iteration index), so a CI run can hand a
+//! developer an exact `--seed` to pass back in.
+
+use std::panic;
+
+use omo::testing::{
+    assert_replay_reproduces_after_state, assert_shadow_matches_engine, assert_state_root_stable,
+    load_raw_image,
+};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+const ENTRYPOINT: u64 = 0x1000;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut seed = 0u64;
+    let mut iterations = 1000u64;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => seed = args.next().and_then(|v| v.parse().ok()).unwrap_or(seed),
+            "--iterations" => {
+                iterations = args.next().and_then(|v| v.parse().ok()).unwrap_or(iterations)
+            }
+            other => eprintln!("ignoring unknown argument: {other}"),
+        }
+    }
+
+    for i in 0..iterations {
+        let run_seed = seed.wrapping_add(i);
+        let result = panic::catch_unwind(|| run_once(run_seed));
+        if let Err(payload) = result {
+            let message = payload
+                .downcast_ref::<String>()
+                .cloned()
+                .or_else(|| payload.downcast_ref::<&str>().map(|s| s.to_string()))
+                .unwrap_or_else(|| "<non-string panic payload>".to_string());
+            eprintln!("reproducing seed: {run_seed}\nfailure: {message}");
+            std::process::exit(1);
+        }
+    }
+    println!("{iterations} iterations from seed {seed} passed");
+}
+
+fn run_once(seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let code_len = 4 * rng.gen_range(1..=16);
+    let code: Vec<u8> = (0..code_len).map(|_| rng.gen()).collect();
+
+    let memory_regions = rng.gen_range(0..=4);
+    let initial_memory: Vec<(u32, Vec<u8>)> = (0..memory_regions)
+        .map(|_| {
+            let addr = rng.gen_range(0x2000u32..0x8000u32) & !0x3;
+            let len = 4 * rng.gen_range(1..=8);
+            (addr, (0..len).map(|_| rng.gen()).collect())
+        })
+        .collect();
+
+    let mut emu = match load_raw_image(&code, &initial_memory, ENTRYPOINT) {
+        Ok(emu) => emu,
+        Err(_) => return,
+    };
+
+    let steps = rng.gen_range(1..=8);
+    for _ in 0..steps {
+        let count = rng.gen_range(1..=8);
+        let change = match emu.run_until(ENTRYPOINT, None, None, count) {
+            Ok(change) => change,
+            Err(_) => return,
+        };
+
+        assert_shadow_matches_engine(&emu, &change.access);
+        assert_state_root_stable(&change.state_after);
+        assert_replay_reproduces_after_state(&change);
+    }
+}
@@ -0,0 +1,108 @@
+//! AEAD sealing/opening of snapshot artifacts: a small header (algorithm, salt, nonce) followed
+//! by the ciphertext, with the key derived from a passphrase via Argon2id.
+
+use aes_gcm::{aead::Aead, Aes256Gcm, KeyInit as _, Nonce as AesNonce};
+use argon2::Argon2;
+use chacha20poly1305::{ChaCha20Poly1305, Nonce as ChaChaNonce};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::EmulatorError;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+
+/// AEAD cipher used to seal a snapshot file.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    Aes256Gcm,
+    ChaCha20Poly1305,
+}
+
+/// Header written ahead of the ciphertext: enough to re-derive the key and re-run the AEAD.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Header {
+    algorithm: Algorithm,
+    salt: [u8; SALT_LEN],
+    nonce: [u8; NONCE_LEN],
+}
+
+fn derive_key(passphrase: &str, salt: &[u8; SALT_LEN]) -> Result<[u8; KEY_LEN], EmulatorError> {
+    let mut key = [0u8; KEY_LEN];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| EmulatorError::KeyDerivation(e.to_string()))?;
+    Ok(key)
+}
+
+/// Encrypt `plaintext` under `passphrase` with a freshly generated salt and nonce, returning
+/// the header-prefixed ciphertext ready to be written to disk.
+pub fn seal(
+    algorithm: Algorithm,
+    passphrase: &str,
+    plaintext: &[u8],
+) -> Result<Vec<u8>, EmulatorError> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+
+    let key = derive_key(passphrase, &salt)?;
+    let ciphertext = match algorithm {
+        Algorithm::Aes256Gcm => {
+            let cipher = Aes256Gcm::new_from_slice(&key)
+                .map_err(|e| EmulatorError::Encryption(format!("bad key: {e}")))?;
+            cipher
+                .encrypt(AesNonce::from_slice(&nonce), plaintext)
+                .map_err(|e| EmulatorError::Encryption(format!("encryption failed: {e}")))?
+        }
+        Algorithm::ChaCha20Poly1305 => {
+            let cipher = ChaCha20Poly1305::new_from_slice(&key)
+                .map_err(|e| EmulatorError::Encryption(format!("bad key: {e}")))?;
+            cipher
+                .encrypt(ChaChaNonce::from_slice(&nonce), plaintext)
+                .map_err(|e| EmulatorError::Encryption(format!("encryption failed: {e}")))?
+        }
+    };
+
+    let header = Header {
+        algorithm,
+        salt,
+        nonce,
+    };
+    let mut out = serde_json::to_vec(&header)
+        .map_err(|e| EmulatorError::Encryption(format!("header encoding failed: {e}")))?;
+    out.push(b'\n');
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+/// Verify the AEAD tag and decrypt a buffer produced by [`seal`], returning the plaintext.
+pub fn open(passphrase: &str, sealed: &[u8]) -> Result<Vec<u8>, EmulatorError> {
+    let newline = sealed
+        .iter()
+        .position(|&b| b == b'\n')
+        .ok_or_else(|| EmulatorError::Decryption("missing header".into()))?;
+    let header: Header = serde_json::from_slice(&sealed[..newline])
+        .map_err(|e| EmulatorError::Decryption(format!("bad header: {e}")))?;
+    let ciphertext = &sealed[newline + 1..];
+
+    let key = derive_key(passphrase, &header.salt)?;
+    match header.algorithm {
+        Algorithm::Aes256Gcm => {
+            let cipher = Aes256Gcm::new_from_slice(&key)
+                .map_err(|e| EmulatorError::Decryption(format!("bad key: {e}")))?;
+            cipher
+                .decrypt(AesNonce::from_slice(&header.nonce), ciphertext)
+                .map_err(|_| EmulatorError::Decryption("AEAD tag verification failed".into()))
+        }
+        Algorithm::ChaCha20Poly1305 => {
+            let cipher = ChaCha20Poly1305::new_from_slice(&key)
+                .map_err(|e| EmulatorError::Decryption(format!("bad key: {e}")))?;
+            cipher
+                .decrypt(ChaChaNonce::from_slice(&header.nonce), ciphertext)
+                .map_err(|_| EmulatorError::Decryption("AEAD tag verification failed".into()))
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! Minimal MIPS32 disassembler: decodes R/J/I-format instruction words into a structured
+//! [`Instruction`] plus mnemonic, for step traces and [`MemAccess`](crate::emulator::MemAccess).
+
+use serde::{Deserialize, Serialize};
+
+/// The decoded instruction format, carrying the fields relevant to that encoding.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Register-to-register ALU/shift ops: opcode 0, dispatched on `funct`.
+    R {
+        rs: u8,
+        rt: u8,
+        rd: u8,
+        shamt: u8,
+        funct: u8,
+    },
+    /// Unconditional jumps: opcode 2 (`j`) / 3 (`jal`).
+    J { target: u32 },
+    /// Everything else: loads/stores, branches, immediate ALU ops.
+    I { rs: u8, rt: u8, imm: i16 },
+}
+
+/// A single decoded MIPS32 instruction.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Instruction {
+    pub opcode: u8,
+    pub format: Format,
+    pub mnemonic: String,
+}
+
+/// Decode a 32-bit big-endian MIPS instruction word fetched at `pc`.
+///
+/// `pc` is only used to reconstruct the absolute target of J-format jumps (the top 4 bits of
+/// the target come from the delay slot's PC, per the MIPS spec).
+pub fn decode(word: u32, pc: u64) -> Instruction {
+    let opcode = ((word >> 26) & 0x3f) as u8;
+    match opcode {
+        0 => {
+            let rs = ((word >> 21) & 0x1f) as u8;
+            let rt = ((word >> 16) & 0x1f) as u8;
+            let rd = ((word >> 11) & 0x1f) as u8;
+            let shamt = ((word >> 6) & 0x1f) as u8;
+            let funct = (word & 0x3f) as u8;
+            Instruction {
+                opcode,
+                format: Format::R {
+                    rs,
+                    rt,
+                    rd,
+                    shamt,
+                    funct,
+                },
+                mnemonic: r_mnemonic(funct)
+                    .map(String::from)
+                    .unwrap_or_else(|| format!("unknown(0x{funct:02x})")),
+            }
+        }
+        2 | 3 => {
+            let raw_target = word & 0x03ff_ffff;
+            let target = ((pc.wrapping_add(4) as u32) & 0xf000_0000) | (raw_target << 2);
+            Instruction {
+                opcode,
+                format: Format::J { target },
+                mnemonic: (if opcode == 2 { "j" } else { "jal" }).to_string(),
+            }
+        }
+        _ => {
+            let rs = ((word >> 21) & 0x1f) as u8;
+            let rt = ((word >> 16) & 0x1f) as u8;
+            let imm = (word & 0xffff) as u16 as i16;
+            Instruction {
+                opcode,
+                format: Format::I { rs, rt, imm },
+                mnemonic: i_mnemonic(opcode)
+                    .map(String::from)
+                    .unwrap_or_else(|| format!("unknown(0x{opcode:02x})")),
+            }
+        }
+    }
+}
+
+fn r_mnemonic(funct: u8) -> Option<&'static str> {
+    Some(match funct {
+        0x20 => "add",
+        0x21 => "addu",
+        0x22 => "sub",
+        0x23 => "subu",
+        0x24 => "and",
+        0x25 => "or",
+        0x26 => "xor",
+        0x27 => "nor",
+        0x2a => "slt",
+        0x2b => "sltu",
+        0x00 => "sll",
+        0x02 => "srl",
+        0x03 => "sra",
+        0x08 => "jr",
+        0x09 => "jalr",
+        0x0c => "syscall",
+        _ => return None,
+    })
+}
+
+fn i_mnemonic(opcode: u8) -> Option<&'static str> {
+    Some(match opcode {
+        0x08 => "addi",
+        0x09 => "addiu",
+        0x0c => "andi",
+        0x0d => "ori",
+        0x0e => "xori",
+        0x0a => "slti",
+        0x0b => "sltiu",
+        0x0f => "lui",
+        0x20 => "lb",
+        0x21 => "lh",
+        0x23 => "lw",
+        0x24 => "lbu",
+        0x25 => "lhu",
+        0x28 => "sb",
+        0x29 => "sh",
+        0x2b => "sw",
+        0x04 => "beq",
+        0x05 => "bne",
+        0x06 => "blez",
+        0x07 => "bgtz",
+        _ => return None,
+    })
+}
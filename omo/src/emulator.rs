@@ -13,17 +13,32 @@ use ethtrie_codec::{EthTrieLayout, KeccakHasher, RlpNodeCodec};
 use crate::{
     arch::{ArchInfo, ArchT},
     config::OmoConfig,
+    crypto::{self, Algorithm},
+    disasm,
     engine::{Engine, Machine, MemoryState},
     errors::EmulatorError,
     loader::{ElfLoader, LoadInfo},
+    merkle,
     os::Runner,
     registers::{RegisterState, Registers},
+    trap::{ExitReason, Timer},
+    trie_cache::TrieCache,
 };
 
 pub struct Emulator<'a, A, Os> {
     config: OmoConfig,
     core: Engine<'a, A>,
     os: Os,
+    /// Fault classified by the unmapped-access hook installed in `new` (or `TimerFired`, set by
+    /// the hook installed in `set_timer`), consumed by `run`.
+    fault: Rc<RefCell<Option<ExitReason>>>,
+    /// Last instruction decoded by the code hook, used to detect syscall-initiated exits.
+    last_instruction: Rc<RefCell<Option<disasm::Instruction>>>,
+    /// Handle for the code hook installed by `set_timer`, kept so a later call can remove it;
+    /// the `Timer` itself is kept alive by the hook closure's own `Rc` clone.
+    timer_hook: Option<unicorn_engine::unicorn_const::uc_hook>,
+    /// Incrementally-updated state root, seeded lazily on first use; see `state_root`.
+    trie_cache: Rc<RefCell<Option<TrieCache>>>,
 }
 
 impl<'a, A, O> Emulator<'a, A, O> {
@@ -41,13 +56,15 @@ impl<'a, A: ArchT, O: Runner> Emulator<'a, A, O> {
         // let binary = binary.as_ref();
         // let load_result = ElfLoader::load(&config.os, binary, argv, &mut machine)?;
         // os.on_load(&mut machine, load_result.clone())?;
+        let trie_cache: Rc<RefCell<Option<TrieCache>>> = Rc::new(RefCell::new(None));
         machine.add_mem_hook(
             HookType::MEM_WRITE | HookType::MEM_READ_AFTER,
             0,
             //align_up((conf.os.stack_address + conf.os.stack_size) as u32, 32) as u64,
             u32::MAX as u64,
             {
-                |uc, mem_type, addr, size, value| {
+                let trie_cache = trie_cache.clone();
+                move |uc, mem_type, addr, size, value| {
                     trace!("{:?} -> ({},{}), v: {}", mem_type, addr, size, value);
                     match mem_type {
                         MemType::WRITE => {
@@ -59,6 +76,11 @@ impl<'a, A: ArchT, O: Runner> Emulator<'a, A, O> {
                                 .state
                                 .memory
                                 .write_value(addr, size, value);
+                            if let Some(cache) = trie_cache.borrow_mut().as_mut() {
+                                let aligned = addr & !0x3;
+                                let word = uc.get_data().state.memory.read_bytes(aligned, 4);
+                                cache.apply_memory_write(aligned, &word);
+                            }
                         }
                         MemType::READ_AFTER => {
                             debug_assert_eq!(
@@ -73,16 +95,47 @@ impl<'a, A: ArchT, O: Runner> Emulator<'a, A, O> {
             },
         )?;
 
+        let fault = Rc::new(RefCell::new(None));
+        machine.add_mem_hook(
+            HookType::MEM_READ_UNMAPPED | HookType::MEM_WRITE_UNMAPPED | HookType::MEM_FETCH_INVALID,
+            0,
+            u32::MAX as u64,
+            {
+                let fault = fault.clone();
+                move |_uc, mem_type, addr, _size, _value| {
+                    let reason = match mem_type {
+                        MemType::READ_UNMAPPED => ExitReason::UnmappedRead { addr },
+                        MemType::WRITE_UNMAPPED => ExitReason::UnmappedWrite { addr },
+                        _ => ExitReason::InvalidFetch { addr },
+                    };
+                    *fault.borrow_mut() = Some(reason);
+                    false
+                }
+            },
+        )?;
+
+        let last_instruction = Rc::new(RefCell::new(None));
         machine.add_code_hook(0, u32::MAX as u64, {
-            |uc, addr, size| {
+            let last_instruction = last_instruction.clone();
+            move |uc, addr, size| {
                 uc.get_data_mut().state.steps += 1;
+                let pc = uc.pc_read().unwrap();
+                let inst = uc.mem_read_as_vec(addr, 4).ok().and_then(|bytes| {
+                    bytes
+                        .as_chunks()
+                        .0
+                        .first()
+                        .map(|word| disasm::decode(u32::from_be_bytes(*word), pc))
+                });
                 debug!(
-                    "step {}, {} {}, pc {}",
+                    "step {}, {} {}, pc {}, inst {:?}",
                     uc.get_data().state.steps,
                     addr,
                     size,
-                    uc.pc_read().unwrap()
+                    pc,
+                    inst
                 );
+                *last_instruction.borrow_mut() = inst;
             }
         })?;
 
@@ -94,9 +147,61 @@ impl<'a, A: ArchT, O: Runner> Emulator<'a, A, O> {
             config: conf,
             core: machine,
             os,
+            fault,
+            last_instruction,
+            timer_hook: None,
+            trie_cache,
         })
     }
 
+    /// Current state root. Seeds the incremental [`TrieCache`] from a from-scratch rebuild on
+    /// first use, then re-prices only the register leaf on every subsequent call -- memory
+    /// writes are already reflected by the mem hook installed in `new`. In debug builds this is
+    /// cross-checked against `EmulatorState::state_root`'s from-scratch rebuild, which remains
+    /// available as a verification fallback.
+    pub fn state_root(&self) -> Result<[u8; 32], EmulatorError> {
+        let regs = self.core.save_registers()?;
+        let mut cache_slot = self.trie_cache.borrow_mut();
+        if cache_slot.is_none() {
+            *cache_slot = Some(TrieCache::seed(&self.save()?));
+        }
+        let cache = cache_slot.as_mut().unwrap();
+        cache.apply_register_update(&regs);
+
+        #[cfg(debug_assertions)]
+        cache.debug_assert_matches_full_rebuild(&self.save()?);
+
+        Ok(cache.root())
+    }
+
+    /// Install a deterministic, instruction-counted timer, replacing any previously installed
+    /// one. Unlike a wall-clock timeout, this makes the step boundary it interrupts at
+    /// reproducible across independent re-executions of the same program.
+    pub fn set_timer(&mut self, timer: Timer) -> Result<(), EmulatorError> {
+        if let Some(old) = self.timer_hook.take() {
+            self.core.remove_hook(old)?;
+        }
+        let timer = Rc::new(RefCell::new(timer));
+        let fault = self.fault.clone();
+        let handle = self.core.add_code_hook(0, u32::MAX as u64, {
+            let timer = timer.clone();
+            move |uc, _addr, _size| {
+                let mut timer = timer.borrow_mut();
+                if timer.remaining == 0 {
+                    return;
+                }
+                timer.remaining -= 1;
+                if timer.remaining == 0 {
+                    (timer.on_fire)();
+                    *fault.borrow_mut() = Some(ExitReason::TimerFired);
+                    let _ = uc.emu_stop();
+                }
+            }
+        })?;
+        self.timer_hook = Some(handle);
+        Ok(())
+    }
+
     pub fn load(
         &mut self,
         binary: impl AsRef<[u8]>,
@@ -121,15 +226,40 @@ impl<'a, A: ArchT, O: Runner> Emulator<'a, A, O> {
         exitpoint: Option<u64>,
         timeout: Option<u64>,
         count: Option<usize>,
-    ) -> Result<u64, EmulatorError> {
+    ) -> Result<RunOutcome, EmulatorError> {
         let exitpoint = exitpoint.unwrap_or_else(|| default_exitpoint(self.core.pointer_size()));
-        self.core.emu_start(
+        let requested_count = count.unwrap_or_default();
+        let steps_before = self.core.get_data().state.steps;
+        *self.fault.borrow_mut() = None;
+
+        let result = self.core.emu_start(
             entrypoint,
             exitpoint,
             timeout.unwrap_or_default(),
-            count.unwrap_or_default(),
-        )?;
-        Ok(self.core.get_data().state.steps)
+            requested_count,
+        );
+        let steps = self.core.get_data().state.steps;
+
+        if let Some(reason) = self.fault.borrow_mut().take() {
+            return Ok(RunOutcome { steps, reason });
+        }
+        result?;
+
+        let reason = if requested_count != 0 && (steps - steps_before) as usize >= requested_count
+        {
+            ExitReason::InstructionCountReached
+        } else if matches!(self.last_instruction.borrow().as_ref(), Some(i) if i.mnemonic == "syscall")
+        {
+            ExitReason::Syscall { pc: self.core.pc()? }
+        } else if timeout.is_some() && self.core.pc()? != exitpoint {
+            // emu_start returns Ok(()) both when exitpoint is reached and when the wall-clock
+            // timeout elapses first; the two are only distinguishable by whether we actually
+            // stopped at exitpoint.
+            ExitReason::TimedOut
+        } else {
+            ExitReason::Normal
+        };
+        Ok(RunOutcome { steps, reason })
     }
 
     pub fn run_until(
@@ -152,19 +282,22 @@ impl<'a, A: ArchT, O: Runner> Emulator<'a, A, O> {
         };
 
         let mem_access_sequence = Rc::new(RefCell::new(vec![]));
+        let pc = self.core.pc_read()?;
+        let fetched = u32::from_be_bytes(
+            *self
+                .core
+                .mem_read_as_vec(pc, 4)?
+                .as_chunks()
+                .0
+                .first()
+                .unwrap(),
+        );
         mem_access_sequence.borrow_mut().push(MemAccess {
             write: false,
-            addr: self.core.pc_read()?,
+            addr: pc,
             size: 4,
-            value: u32::from_be_bytes(
-                *self
-                    .core
-                    .mem_read_as_vec(self.core.pc_read()?, 4)?
-                    .as_chunks()
-                    .0
-                    .first()
-                    .unwrap(),
-            ) as i64,
+            value: fetched as i64,
+            disasm: Some(disasm::decode(fetched, pc)),
         });
 
         let handle = self.core.add_mem_hook(
@@ -173,7 +306,7 @@ impl<'a, A: ArchT, O: Runner> Emulator<'a, A, O> {
             u32::MAX as u64,
             {
                 let mem_access = mem_access_sequence.clone();
-                move |_uc, mem_type, addr, size, value| {
+                move |uc, mem_type, addr, size, value| {
                     match mem_type {
                         MemType::WRITE => {
                             mem_access.borrow_mut().push(MemAccess {
@@ -181,14 +314,26 @@ impl<'a, A: ArchT, O: Runner> Emulator<'a, A, O> {
                                 addr,
                                 size,
                                 value,
+                                disasm: None,
                             });
                         }
                         MemType::READ_AFTER | MemType::READ | MemType::FETCH => {
+                            // `value` is not guaranteed to be the fetched word (it's whatever
+                            // Unicorn happens to report for this access type), so re-read the
+                            // bytes ourselves rather than trust it -- same approach as the
+                            // primary fetch above.
+                            let disasm = (mem_type == MemType::FETCH && size == 4)
+                                .then(|| uc.mem_read_as_vec(addr, 4).ok())
+                                .flatten()
+                                .map(|bytes| {
+                                    disasm::decode(u32::from_be_bytes(bytes.try_into().unwrap()), addr)
+                                });
                             mem_access.borrow_mut().push(MemAccess {
                                 write: false,
                                 addr,
                                 size,
                                 value,
+                                disasm,
                             });
                         }
                         _ => {}
@@ -232,6 +377,14 @@ pub fn default_exitpoint(point_size: u8) -> u64 {
     }
 }
 
+/// Result of a [`Emulator::run`] call: the total instruction count retired so far, and a
+/// classification of why execution stopped.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct RunOutcome {
+    pub steps: u64,
+    pub reason: ExitReason,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct StateChange {
     pub state_before: EmulatorState,
@@ -273,16 +426,87 @@ impl StateChange {
             &self.access,
         )
         .unwrap();
+        serde_json::to_writer_pretty(
+            std::fs::File::options()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(output_dir.join("merkle_proof.json"))
+                .unwrap(),
+            &merkle::prove_step(self).unwrap(),
+        )
+        .unwrap();
+    }
+
+    /// Like [`output_to`](Self::output_to), but each artifact is sealed with `algorithm` under
+    /// `passphrase` before being written, so the files are both confidential and tamper-evident.
+    pub fn output_to_encrypted(
+        &self,
+        output_dir: PathBuf,
+        algorithm: Algorithm,
+        passphrase: &str,
+    ) -> Result<(), EmulatorError> {
+        create_dir_all(&output_dir)?;
+        write_encrypted(
+            &output_dir.join("before_state.json.enc"),
+            algorithm,
+            passphrase,
+            &self.state_before,
+        )?;
+        write_encrypted(
+            &output_dir.join("after_state.json.enc"),
+            algorithm,
+            passphrase,
+            &self.state_after,
+        )?;
+        write_encrypted(
+            &output_dir.join("mem_access.json.enc"),
+            algorithm,
+            passphrase,
+            &self.access,
+        )?;
+        write_encrypted(
+            &output_dir.join("merkle_proof.json.enc"),
+            algorithm,
+            passphrase,
+            &merkle::prove_step(self)?,
+        )?;
+        Ok(())
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+fn write_encrypted<T: Serialize>(
+    path: &std::path::Path,
+    algorithm: Algorithm,
+    passphrase: &str,
+    value: &T,
+) -> Result<(), EmulatorError> {
+    let plaintext = serde_json::to_vec(value)?;
+    let sealed = crypto::seal(algorithm, passphrase, &plaintext)?;
+    std::fs::write(path, sealed)?;
+    Ok(())
+}
+
+/// Read and decrypt a snapshot artifact written by [`StateChange::output_to_encrypted`],
+/// verifying its AEAD tag before deserializing.
+pub fn load_encrypted<T: for<'de> Deserialize<'de>>(
+    path: &std::path::Path,
+    passphrase: &str,
+) -> Result<T, EmulatorError> {
+    let sealed = std::fs::read(path)?;
+    let plaintext = crypto::open(passphrase, &sealed)?;
+    Ok(serde_json::from_slice(&plaintext)?)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MemAccess {
     /// read or write
     pub write: bool,
     pub addr: u64,
     pub size: usize,
     pub value: i64,
+    /// Decoded instruction, present only for the fetch that produced this access.
+    pub disasm: Option<disasm::Instruction>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -292,33 +516,55 @@ pub struct EmulatorState {
     pub steps: u64,
 }
 
-impl EmulatorState {
-    pub fn state_root(&self) -> [u8; 32] {
-        let mut root = Default::default();
-        let mem: BTreeMap<_, _> = self.memories.clone().into();
-        let mut db = memory_db::MemoryDB::<KeccakHasher, memory_db::HashKey<KeccakHasher>, _>::new(
-            RlpNodeCodec::empty_node(),
-        );
+/// Trie key for a memory word at `addr`, matching the encoding `EmulatorState::state_root` uses.
+pub(crate) fn memory_key(addr: u64) -> [u8; 4] {
+    ((addr >> 2) as u32).to_be_bytes()
+}
+
+/// Trie key for the packed register leaf (`[0, 0, 0, 0]`).
+pub(crate) fn register_key() -> [u8; 4] {
+    0u32.to_be_bytes()
+}
+
+pub(crate) fn encode_registers(regs: &RegisterState) -> Vec<u8> {
+    let mut encoder = rlp::RlpStream::new_list(regs.len());
+    for (reg_id, v) in regs.clone() {
+        let encoded_register = ((reg_id as u64) << 32) + v;
+        encoder.append_iter(encoded_register.to_be_bytes());
+    }
+    encoder.out().to_vec()
+}
+
+/// Populate a fresh `MemoryDB` with `state`'s memory words and register leaf under the
+/// `EthTrieLayout` used for `state_root`, returning the backing DB alongside the root instead of
+/// discarding it. Kept `pub(crate)` so [`crate::merkle`] can query individual keys with proof
+/// recording against the same trie `state_root` commits.
+pub(crate) fn build_trie(
+    state: &EmulatorState,
+) -> (
+    memory_db::MemoryDB<KeccakHasher, memory_db::HashKey<KeccakHasher>, Vec<u8>>,
+    [u8; 32],
+) {
+    let mut root = Default::default();
+    let mem: BTreeMap<_, _> = state.memories.clone().into();
+    let mut db = memory_db::MemoryDB::<KeccakHasher, memory_db::HashKey<KeccakHasher>, _>::new(
+        RlpNodeCodec::empty_node(),
+    );
+    {
         let mut trie = trie_db::TrieDBMutBuilder::<EthTrieLayout>::new(&mut db, &mut root).build();
         for (addr, v) in mem {
-            let shortend_addr = (addr >> 2) as u32;
-            trie.insert(&shortend_addr.to_be_bytes(), v.as_slice())
-                .unwrap();
+            trie.insert(&memory_key(addr), v.as_slice()).unwrap();
         }
-
         // insert registers as a leaf with key [0,0,0,0]
-        let regs = {
-            let mut encoder = rlp::RlpStream::new_list(self.regs.len());
-            for (reg_id, v) in self.regs.clone() {
-                let encoded_register = ((reg_id as u64) << 32) + v;
-                encoder.append_iter(encoded_register.to_be_bytes());
-            }
-            encoder.out().to_vec()
-        };
-        trie.insert(&0u32.to_be_bytes(), &regs).unwrap();
-
+        trie.insert(&register_key(), &encode_registers(&state.regs))
+            .unwrap();
         trie.commit();
-        drop(trie);
-        root
+    }
+    (db, root)
+}
+
+impl EmulatorState {
+    pub fn state_root(&self) -> [u8; 32] {
+        build_trie(self).1
     }
 }
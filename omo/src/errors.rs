@@ -0,0 +1,31 @@
+//! Error types shared across the emulator core.
+
+use thiserror::Error;
+
+/// Errors that can occur while loading, running or snapshotting an [`Emulator`](crate::emulator::Emulator).
+#[derive(Error, Debug)]
+pub enum EmulatorError {
+    #[error("unicorn engine error: {0}")]
+    Unicorn(#[from] unicorn_engine::unicorn_const::uc_error),
+
+    #[error("failed to load binary: {0}")]
+    Load(String),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("failed to (de)serialize emulator state: {0}")]
+    Serde(#[from] serde_json::Error),
+
+    #[error("key derivation failed: {0}")]
+    KeyDerivation(String),
+
+    #[error("failed to encrypt snapshot: {0}")]
+    Encryption(String),
+
+    #[error("failed to decrypt snapshot: {0}")]
+    Decryption(String),
+
+    #[error("failed to generate merkle proof: {0}")]
+    Proof(String),
+}
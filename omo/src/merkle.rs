@@ -0,0 +1,64 @@
+//! Compact Merkle proofs over the `EthTrieLayout` memory/register trie built by
+//! [`EmulatorState::state_root`](crate::emulator::EmulatorState::state_root).
+
+use serde::{Deserialize, Serialize};
+use trie_db::proof::generate_proof;
+
+use ethtrie_codec::EthTrieLayout;
+
+use crate::{
+    emulator::{build_trie, memory_key, register_key, EmulatorState, MemAccess, StateChange},
+    errors::EmulatorError,
+};
+
+/// Sibling node encodings proving a set of keys' values under a single root.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MerkleProof {
+    pub nodes: Vec<Vec<u8>>,
+}
+
+/// Prove that every key in `keys` is present (with its current value) under `state`'s root.
+fn prove_keys(state: &EmulatorState, keys: &[[u8; 4]]) -> Result<MerkleProof, EmulatorError> {
+    let (db, root) = build_trie(state);
+    let nodes = generate_proof::<_, EthTrieLayout, _, _>(&db, &root, keys.iter().map(|k| k.as_slice()))
+        .map_err(|e| EmulatorError::Proof(format!("{e:?}")))?;
+    Ok(MerkleProof { nodes })
+}
+
+/// Proof that a single step's reads were present under `state_before.state_root()`, and that its
+/// writes transition to `state_after.state_root()`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StepProof {
+    /// Proves every read address (plus the register leaf) under `state_before`'s root.
+    pub reads: MerkleProof,
+    /// Proves every written address under `state_after`'s root.
+    pub writes: MerkleProof,
+}
+
+/// Generate the [`StepProof`] for a [`StateChange`], covering exactly the cells its `access`
+/// list touched.
+pub fn prove_step(change: &StateChange) -> Result<StepProof, EmulatorError> {
+    let mut read_keys: Vec<[u8; 4]> = change
+        .access
+        .iter()
+        .filter(|a: &&MemAccess| !a.write)
+        .map(|a| memory_key(a.addr))
+        .collect();
+    read_keys.push(register_key());
+    read_keys.sort();
+    read_keys.dedup();
+
+    let mut write_keys: Vec<[u8; 4]> = change
+        .access
+        .iter()
+        .filter(|a: &&MemAccess| a.write)
+        .map(|a| memory_key(a.addr))
+        .collect();
+    write_keys.sort();
+    write_keys.dedup();
+
+    Ok(StepProof {
+        reads: prove_keys(&change.state_before, &read_keys)?,
+        writes: prove_keys(&change.state_after, &write_keys)?,
+    })
+}
@@ -0,0 +1,86 @@
+//! Shared plumbing for the differential memory-consistency harness.
+//!
+//! Used by both the `cargo fuzz` target (`fuzz/fuzz_targets/differential_memory.rs`) and the
+//! deterministic, seeded driver binary (`src/bin/differential_driver.rs`) so the two stay in
+//! sync: both load images and check invariants through the same functions.
+
+use crate::{
+    arch::mips::Mips32,
+    emulator::{Emulator, EmulatorState, StateChange},
+    errors::EmulatorError,
+    os::NullOs,
+};
+
+/// Build a minimal [`Emulator`] around a raw (non-ELF) code image and a set of pre-seeded memory
+/// regions, entering execution at `entrypoint`. Intended only for the differential harness,
+/// where we want full control over the bytes fed to the decoder without going through the ELF
+/// loader.
+pub fn load_raw_image(
+    code: &[u8],
+    initial_memory: &[(u32, Vec<u8>)],
+    entrypoint: u64,
+) -> Result<Emulator<'static, Mips32, NullOs>, EmulatorError> {
+    let mut emu = Emulator::new(
+        crate::config::OmoConfig::default(),
+        Mips32,
+        unicorn_engine::unicorn_const::Mode::MIPS32,
+        NullOs::default(),
+    )?;
+    emu.engine().mem_write(entrypoint, code)?;
+    for (addr, bytes) in initial_memory {
+        emu.engine().mem_write(*addr as u64, bytes)?;
+    }
+    Ok(emu)
+}
+
+/// Every address touched by `access` must read back identically from the live Unicorn engine
+/// and from the `MemoryState` shadow kept in `emu`'s hook. Panics naming the diverging address
+/// on mismatch; unlike the `debug_assert_eq!` in `Emulator::new`'s mem hook, this also runs in
+/// release builds.
+pub fn assert_shadow_matches_engine<A: crate::arch::ArchT, O: crate::os::Runner>(
+    emu: &Emulator<'static, A, O>,
+    access: &[crate::emulator::MemAccess],
+) {
+    for a in access {
+        let from_engine = emu
+            .engine()
+            .mem_read_as_vec(a.addr, a.size)
+            .expect("engine read");
+        let from_shadow = emu
+            .engine()
+            .get_data()
+            .state
+            .memory
+            .read_bytes(a.addr, a.size);
+        assert_eq!(
+            from_engine, from_shadow,
+            "shadow memory diverged from engine at addr {:#x}",
+            a.addr
+        );
+    }
+}
+
+/// `state_root()` must be stable across repeated calls on the same snapshot.
+pub fn assert_state_root_stable(state_after: &EmulatorState) {
+    let root_a = state_after.state_root();
+    let root_b = state_after.state_root();
+    assert_eq!(
+        root_a, root_b,
+        "state_root() is not stable across repeated calls"
+    );
+}
+
+/// Replaying `change`'s write sequence onto `state_before`'s memory must reproduce
+/// `state_after`'s memory exactly.
+pub fn assert_replay_reproduces_after_state(change: &StateChange) {
+    let mut replayed = change.state_before.memories.clone();
+    for a in &change.access {
+        if a.write {
+            replayed.write_value(a.addr, a.size, a.value);
+        }
+    }
+    assert_eq!(
+        replayed, change.state_after.memories,
+        "replaying the write sequence onto state_before did not reproduce state_after"
+    );
+}
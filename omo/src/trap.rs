@@ -0,0 +1,55 @@
+//! Structured classification of why a [`run`](crate::emulator::Emulator::run) call stopped,
+//! plus a deterministic, step-counted timer (instructions, not wall-clock time).
+
+use serde::{Deserialize, Serialize};
+
+/// Why a call to [`Emulator::run`](crate::emulator::Emulator::run) returned.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitReason {
+    /// Execution reached `exitpoint`.
+    Normal,
+    /// The requested instruction `count` was reached before `exitpoint`.
+    InstructionCountReached,
+    /// A [`Timer`] installed with [`Emulator::set_timer`](crate::emulator::Emulator::set_timer)
+    /// fired.
+    TimerFired,
+    /// The wall-clock `timeout` passed to [`Emulator::run`](crate::emulator::Emulator::run)
+    /// elapsed before execution reached `exitpoint`.
+    TimedOut,
+    /// A fetch from `addr` hit unmapped or non-executable memory.
+    InvalidFetch { addr: u64 },
+    /// A read from unmapped memory at `addr`.
+    UnmappedRead { addr: u64 },
+    /// A write to unmapped memory at `addr`.
+    UnmappedWrite { addr: u64 },
+    /// The last instruction executed before stopping was a `syscall`.
+    Syscall { pc: u64 },
+}
+
+/// A deterministic countdown, measured in emulated instructions rather than wall-clock time.
+///
+/// Installed via [`Emulator::set_timer`](crate::emulator::Emulator::set_timer), it decrements
+/// once per instruction retired (the same counter driving `EngineState::steps`) and, once it
+/// reaches zero, runs its callback and requests that the engine stop.
+pub struct Timer {
+    pub(crate) remaining: u64,
+    pub(crate) on_fire: Box<dyn FnMut()>,
+}
+
+impl Timer {
+    /// Fire after `instructions` more instructions have retired.
+    pub fn after(instructions: u64, on_fire: impl FnMut() + 'static) -> Self {
+        Self {
+            remaining: instructions,
+            on_fire: Box::new(on_fire),
+        }
+    }
+}
+
+impl std::fmt::Debug for Timer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Timer")
+            .field("remaining", &self.remaining)
+            .finish_non_exhaustive()
+    }
+}
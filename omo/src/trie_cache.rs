@@ -0,0 +1,62 @@
+//! Incrementally-maintained counterpart to `EmulatorState::state_root`'s from-scratch rebuild:
+//! keeps the trie's backing `MemoryDB` and root around between steps instead of discarding them.
+
+use memory_db::{HashKey, MemoryDB};
+use trie_db::{TrieDBMutBuilder, TrieMut};
+
+use ethtrie_codec::{EthTrieLayout, KeccakHasher};
+
+use crate::{
+    emulator::{build_trie, encode_registers, memory_key, register_key, EmulatorState},
+    registers::RegisterState,
+};
+
+/// A persistent `EthTrieLayout` trie, seeded once from a full [`EmulatorState`] and kept up to
+/// date with single-key inserts instead of being rebuilt from scratch.
+pub struct TrieCache {
+    db: MemoryDB<KeccakHasher, HashKey<KeccakHasher>, Vec<u8>>,
+    root: [u8; 32],
+}
+
+impl TrieCache {
+    /// Seed the cache from a full snapshot, using the exact same encoding as
+    /// [`EmulatorState::state_root`] so the two never disagree about what "from scratch" means.
+    pub fn seed(state: &EmulatorState) -> Self {
+        let (db, root) = build_trie(state);
+        Self { db, root }
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.root
+    }
+
+    /// Re-insert the word at `aligned_addr`'s key after a memory write landed in the shadow
+    /// `MemoryState`. `aligned_addr` must be 4-byte aligned; `word` is the full post-write value
+    /// read back from the shadow at that address.
+    pub fn apply_memory_write(&mut self, aligned_addr: u64, word: &[u8]) {
+        let mut trie =
+            TrieDBMutBuilder::<EthTrieLayout>::from_existing(&mut self.db, &mut self.root).build();
+        trie.insert(&memory_key(aligned_addr), word).unwrap();
+        trie.commit();
+    }
+
+    /// Re-insert the register leaf. Registers aren't touched by the memory-write hook that
+    /// drives [`apply_memory_write`], so callers re-run this whenever they need an up-to-date
+    /// root rather than after every instruction.
+    pub fn apply_register_update(&mut self, regs: &RegisterState) {
+        let mut trie =
+            TrieDBMutBuilder::<EthTrieLayout>::from_existing(&mut self.db, &mut self.root).build();
+        trie.insert(&register_key(), &encode_registers(regs)).unwrap();
+        trie.commit();
+    }
+
+    /// Cross-check the incremental root against a from-scratch rebuild of `state`. Debug-only:
+    /// this defeats the whole point of the cache if run on every call in release builds.
+    pub fn debug_assert_matches_full_rebuild(&self, state: &EmulatorState) {
+        debug_assert_eq!(
+            self.root,
+            state.state_root(),
+            "incremental state root diverged from the from-scratch rebuild"
+        );
+    }
+}